@@ -1,9 +1,12 @@
 use super::value_shuffle;
-use bulletproofs::r1cs::ConstraintSystem;
+use bulletproofs::r1cs::{ConstraintSystem, Prover, R1CSProof};
+use bulletproofs::{BulletproofGens, PedersenGens};
+use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::scalar::Scalar;
 use error::SpacesuitError;
+use merlin::Transcript;
 use std::cmp::{max, min};
-use value::{AllocatedValue, Value};
+use value::{AllocatedValue, ProverCommittable, Value, VerifierCommittable};
 
 /// Enforces that the values in `y` are a valid reordering of the values in `x`,
 /// allowing for padding (zero values) in x that can be omitted in y (or the other way around).
@@ -44,6 +47,93 @@ pub fn fill_cs<CS: ConstraintSystem>(
     Ok(())
 }
 
+/// Enforces that the values in `y` are a valid reordering of the values in `x`,
+/// after padding both `x` and `y` up to a fixed capacity `target_len` with
+/// committed zero values. Unlike `fill_cs`, which only pads the shorter side
+/// up to the length of the longer one, this pads *both* sides independently,
+/// so the resulting proof has the same shape (`target_len`, `target_len`)
+/// regardless of how many genuine values `x` and `y` actually contained. This
+/// hides the real value count from anyone observing the proof's vector
+/// lengths, at the cost of requiring `target_len >= max(x.len(), y.len())`.
+pub fn fill_cs_fixed<CS: ConstraintSystem>(
+    cs: &mut CS,
+    mut x: Vec<AllocatedValue>,
+    mut y: Vec<AllocatedValue>,
+    target_len: usize,
+) -> Result<(), SpacesuitError> {
+    if target_len < max(x.len(), y.len()) {
+        return Err(SpacesuitError::InvalidR1CSConstruction);
+    }
+
+    pad_to(cs, &mut x, target_len);
+    pad_to(cs, &mut y, target_len);
+
+    value_shuffle::fill_cs(cs, x, y)?;
+
+    Ok(())
+}
+
+// Appends committed zero values to `values` until it reaches `target_len`.
+fn pad_to<CS: ConstraintSystem>(cs: &mut CS, values: &mut Vec<AllocatedValue>, target_len: usize) {
+    for _ in values.len()..target_len {
+        // We need three independent variables constrained to be zeroes.
+        // We can do that with a single multiplier and two linear constraints for the inputs only.
+        // The multiplication constraint is enough to ensure that the third wire is also zero.
+        let (q, a, t) = cs.multiply(Scalar::zero().into(), Scalar::zero().into());
+        values.push(AllocatedValue {
+            q,
+            a,
+            t,
+            assignment: Some(Value::zero()),
+        });
+    }
+}
+
+/// Returns the number of multipliers that `fill_cs` will allocate for a
+/// shuffle between an `m`-value side and an `n`-value side: one multiplier
+/// per padding value plus whatever `value_shuffle::fill_cs` itself needs for
+/// the resulting `max(m, n)`-length vectors. Callers can use this to size a
+/// `BulletproofGens` before proving instead of guessing a fixed bound.
+///
+/// This counts one padded shuffle only. For a statement composed of several
+/// gadgets, no helper here sums their counts; each gadget's own count has to
+/// be added up by the caller.
+pub fn multiplier_count(m: usize, n: usize) -> usize {
+    let pad_count = max(m, n) - min(m, n);
+    pad_count + value_shuffle::multiplier_count(max(m, n))
+}
+
+/// Proves a padded shuffle of `x` against `y`, growing `bp_gens` to the
+/// capacity the gadget actually needs instead of requiring the caller to
+/// guess a fixed bound up front.
+///
+/// Mirrors the capacity-growth pattern from the upstream `bulletproofs` R1CS
+/// prover: rather than size `bp_gens` for a worst case, we compute the exact
+/// number of multipliers `fill_cs` will allocate for `(x.len(), y.len())`,
+/// round it up to the next power of two, and grow `bp_gens` to that size
+/// before building the constraint system and proving.
+pub fn prove_with_growable_gens(
+    pc_gens: &PedersenGens,
+    bp_gens: &mut BulletproofGens,
+    transcript: &mut Transcript,
+    x: Vec<Value>,
+    y: Vec<Value>,
+) -> Result<(R1CSProof, Vec<CompressedRistretto>, Vec<CompressedRistretto>), SpacesuitError> {
+    let needed = multiplier_count(x.len(), y.len()).next_power_of_two();
+    bp_gens.increase_capacity(needed);
+
+    let mut rng = rand::thread_rng();
+    let mut prover = Prover::new(bp_gens, pc_gens, transcript);
+
+    let (input_com, input_vars) = x.commit(&mut prover, &mut rng);
+    let (output_com, output_vars) = y.commit(&mut prover, &mut rng);
+
+    fill_cs(&mut prover, input_vars, output_vars)?;
+
+    let proof = prover.prove()?;
+    Ok((proof, input_com, output_com))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,6 +205,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn padded_shuffle_growable_gens() {
+        // Start from generators far too small for the shuffle, and rely on
+        // `prove_with_growable_gens` to grow them to the required capacity.
+        let pc_gens = PedersenGens::default();
+        let mut bp_gens = BulletproofGens::new(1, 1);
+
+        let input = vec![peso(1), zero(), yuan(4)];
+        let output = vec![peso(1), yuan(4)];
+
+        let mut prover_transcript = Transcript::new(b"PaddedShuffleGrowableGensTest");
+        let (proof, input_com, output_com) = prove_with_growable_gens(
+            &pc_gens,
+            &mut bp_gens,
+            &mut prover_transcript,
+            input,
+            output,
+        )
+        .unwrap();
+
+        let mut verifier_transcript = Transcript::new(b"PaddedShuffleGrowableGensTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut verifier_transcript);
+
+        let input_vars = input_com.commit(&mut verifier);
+        let output_vars = output_com.commit(&mut verifier);
+
+        assert!(fill_cs(&mut verifier, input_vars, output_vars).is_ok());
+        assert!(verifier.verify(&proof).is_ok());
+    }
+
+    #[test]
+    fn padded_shuffle_fixed() {
+        // Both sides padded up to a fixed capacity (`target_len`), regardless
+        // of how many genuine values they start with.
+        assert!(
+            padded_shuffle_fixed_helper(
+                vec![peso(1), zero(), yuan(4)],
+                vec![peso(1), yuan(4)],
+                4
+            )
+            .is_ok()
+        );
+        assert!(
+            padded_shuffle_fixed_helper(
+                vec![yuan(1), yuan(4), peso(8)],
+                vec![peso(8), yuan(4), yuan(1)],
+                3
+            )
+            .is_ok()
+        );
+        assert!(
+            padded_shuffle_fixed_helper(vec![peso(1), yuan(4)], vec![yuan(4), peso(2)], 2).is_err()
+        );
+    }
+
+    #[test]
+    fn padded_shuffle_fixed_target_len_too_small() {
+        // `target_len` smaller than the longer side must return an error
+        // from `fill_cs_fixed` itself, not panic.
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 1);
+        let mut prover_transcript = Transcript::new(b"PaddedShuffleFixedTooSmallTest");
+        let mut rng = rand::thread_rng();
+        let mut prover = Prover::new(&bp_gens, &pc_gens, &mut prover_transcript);
+
+        let input = vec![peso(1), zero(), yuan(4)];
+        let output = vec![peso(1), yuan(4)];
+        let (_, input_vars) = input.commit(&mut prover, &mut rng);
+        let (_, output_vars) = output.commit(&mut prover, &mut rng);
+
+        // max(input.len(), output.len()) == 3, so target_len == 2 is too small.
+        assert!(fill_cs_fixed(&mut prover, input_vars, output_vars, 2).is_err());
+    }
+
     // Helper functions to make the tests easier to read
     fn yuan(q: u64) -> Value {
         Value {
@@ -167,4 +331,42 @@ mod tests {
         // Verifier verifies proof
         Ok(verifier.verify(&proof)?)
     }
+
+    fn padded_shuffle_fixed_helper(
+        input: Vec<Value>,
+        output: Vec<Value>,
+        target_len: usize,
+    ) -> Result<(), SpacesuitError> {
+        // Common
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(128, 1);
+
+        // Prover's scope
+        let (proof, input_com, output_com) = {
+            let mut prover_transcript = Transcript::new(b"PaddedShuffleFixedTest");
+            let mut rng = rand::thread_rng();
+
+            let mut prover = Prover::new(&bp_gens, &pc_gens, &mut prover_transcript);
+            let (input_com, input_vars) = input.commit(&mut prover, &mut rng);
+            let (output_com, output_vars) = output.commit(&mut prover, &mut rng);
+
+            assert!(fill_cs_fixed(&mut prover, input_vars, output_vars, target_len).is_ok());
+
+            let proof = prover.prove()?;
+            (proof, input_com, output_com)
+        };
+
+        // Verifier makes a `ConstraintSystem` instance representing a shuffle gadget
+        let mut verifier_transcript = Transcript::new(b"PaddedShuffleFixedTest");
+        let mut verifier = Verifier::new(&bp_gens, &pc_gens, &mut verifier_transcript);
+
+        let input_vars = input_com.commit(&mut verifier);
+        let output_vars = output_com.commit(&mut verifier);
+
+        // Verifier adds constraints to the constraint system
+        assert!(fill_cs_fixed(&mut verifier, input_vars, output_vars, target_len).is_ok());
+
+        // Verifier verifies proof
+        Ok(verifier.verify(&proof)?)
+    }
 }